@@ -0,0 +1,178 @@
+use crate::parser::story_parser::story;
+use crate::StoryEvent;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// An error encountered while loading a `.story` file (or one of its
+/// `+INCLUDE`d files) from disk
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+    CyclicInclude { path: PathBuf },
+    UnsafeInclude { path: PathBuf },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            LoadError::Parse { path, message } => write!(f, "{}: {message}", path.display()),
+            LoadError::CyclicInclude { path } => {
+                write!(f, "{}: cyclic +INCLUDE", path.display())
+            }
+            LoadError::UnsafeInclude { path } => {
+                write!(f, "{}: +INCLUDE path escapes the story directory", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Whether `path` can be joined onto the including file's directory without
+/// reading outside it. `.story` files can come from shared/untrusted
+/// sources, so this rejects absolute paths and `..` components the same way
+/// `save::slot_path` rejects them for save slot names.
+fn is_safe_include_path(path: &Path) -> bool {
+    path.is_relative() && !path.components().any(|c| c == Component::ParentDir)
+}
+
+/// Whether the canonicalized `resolved` path is still inside `root`. Unlike
+/// `is_safe_include_path`, this catches a symlink *inside* the story
+/// directory that points outside it (e.g. `story/leak.story -> /etc/passwd`),
+/// which passes the syntactic `..`/absolute check trivially.
+fn is_within_root(resolved: &Path, root: &Path) -> bool {
+    resolved.starts_with(root)
+}
+
+/// Loads a `.story` file, recursively splicing in any `+INCLUDE:path` events
+/// with paths resolved relative to the including file's directory
+///
+/// # Errors
+/// Returns an error if `path` or any file it includes cannot be read or
+/// parsed, if the includes form a cycle, or if an include path is absolute,
+/// contains `..`, or resolves outside the story directory.
+pub fn load_story(path: &Path) -> Result<Vec<StoryEvent>, LoadError> {
+    let root = fs::canonicalize(path)
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf());
+    let mut stack = HashSet::new();
+    load_story_inner(path, &root, &mut stack)
+}
+
+fn load_story_inner(path: &Path, root: &Path, stack: &mut HashSet<PathBuf>) -> Result<Vec<StoryEvent>, LoadError> {
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if !stack.insert(canonical.clone()) {
+        return Err(LoadError::CyclicInclude { path: path.to_path_buf() });
+    }
+
+    let text = fs::read_to_string(path).map_err(|source| LoadError::Io { path: path.to_path_buf(), source })?;
+    let events = story(&text).map_err(|source| LoadError::Parse {
+        path: path.to_path_buf(),
+        message: source.to_string(),
+    })?;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut spliced = Vec::with_capacity(events.len());
+    for event in events {
+        if let StoryEvent::Include(include_path) = event {
+            let include_path = PathBuf::from(include_path);
+            if !is_safe_include_path(&include_path) {
+                return Err(LoadError::UnsafeInclude { path: include_path });
+            }
+            let full_path = dir.join(&include_path);
+            if let Ok(resolved) = fs::canonicalize(&full_path) {
+                if !is_within_root(&resolved, root) {
+                    return Err(LoadError::UnsafeInclude { path: include_path });
+                }
+            }
+            spliced.extend(load_story_inner(&full_path, root, stack)?);
+        } else {
+            spliced.push(event);
+        }
+    }
+
+    stack.remove(&canonical);
+    Ok(spliced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // A fresh scratch directory per test, so parallel test runs don't
+    // stomp on each other's `.story` files
+    fn temp_dir(name: &str) -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("text-adventure-loader-test-{}-{name}-{id}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn splices_an_included_file_into_the_program() {
+        let dir = temp_dir("include");
+        fs::write(dir.join("main.story"), "+INCLUDE:room.story\n+PAUSE").unwrap();
+        fs::write(dir.join("room.story"), "+LABEL:room").unwrap();
+
+        let story = load_story(&dir.join("main.story")).unwrap();
+        assert!(matches!(&story[0], StoryEvent::Label(name) if name == "room"));
+        assert!(matches!(story[1], StoryEvent::Pause));
+    }
+
+    #[test]
+    fn reports_a_cyclic_include_instead_of_recursing_forever() {
+        let dir = temp_dir("cycle");
+        fs::write(dir.join("a.story"), "+INCLUDE:b.story").unwrap();
+        fs::write(dir.join("b.story"), "+INCLUDE:a.story").unwrap();
+
+        let err = load_story(&dir.join("a.story")).unwrap_err();
+        assert!(matches!(err, LoadError::CyclicInclude { .. }));
+    }
+
+    #[test]
+    fn reports_a_missing_file_as_an_io_error() {
+        let dir = temp_dir("missing");
+        let err = load_story(&dir.join("nope.story")).unwrap_err();
+        assert!(matches!(err, LoadError::Io { .. }));
+    }
+
+    #[test]
+    fn rejects_an_include_path_that_escapes_the_story_directory() {
+        let dir = temp_dir("escape-dotdot");
+        fs::write(dir.join("main.story"), "+INCLUDE:../../../../etc/passwd").unwrap();
+
+        let err = load_story(&dir.join("main.story")).unwrap_err();
+        assert!(matches!(err, LoadError::UnsafeInclude { .. }));
+    }
+
+    #[test]
+    fn rejects_an_absolute_include_path() {
+        let dir = temp_dir("escape-absolute");
+        fs::write(dir.join("main.story"), "+INCLUDE:/etc/passwd").unwrap();
+
+        let err = load_story(&dir.join("main.story")).unwrap_err();
+        assert!(matches!(err, LoadError::UnsafeInclude { .. }));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn rejects_a_symlink_that_points_outside_the_story_directory() {
+        let dir = temp_dir("escape-symlink");
+        let outside = temp_dir("escape-symlink-target");
+        fs::write(outside.join("secret.txt"), "+LABEL:leaked").unwrap();
+
+        std::os::unix::fs::symlink(outside.join("secret.txt"), dir.join("leak.story")).unwrap();
+        fs::write(dir.join("main.story"), "+INCLUDE:leak.story").unwrap();
+
+        let err = load_story(&dir.join("main.story")).unwrap_err();
+        assert!(matches!(err, LoadError::UnsafeInclude { .. }));
+    }
+}
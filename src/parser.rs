@@ -1,4 +1,5 @@
-use crate::StoryEvent;
+use crate::text::{TextFragment, TextStyle};
+use crate::{BranchOp, StoryEvent};
 use peg;
 
 peg::parser! { pub grammar story_parser() for str {
@@ -7,33 +8,196 @@ peg::parser! { pub grammar story_parser() for str {
 
     rule eof() = ![_]
 
-    rule event() -> StoryEvent = s:command() / s:text() / expected!("event")
-    
-    // Commands are uppercase words preceded by a plus sign, and can have arguments
+    rule event() -> StoryEvent = s:if_command() / s:command() / s:text() / expected!("event")
+
+    // Commands are uppercase words preceded by a plus sign, and can have arguments.
+    // Commands that require an argument fail the parse (rather than panicking)
+    // when none is given, e.g. a bare `+LABEL` with no `:name`.
     rule command() -> StoryEvent
-        = "+" cmd:$(['A'..='Z']+) _? arg:arg()? { 
-            match cmd {
-                "PAUSE" => StoryEvent::Pause,
-                "CLEAR" => StoryEvent::Clear,
-                "INPUT" => StoryEvent::Input(arg.unwrap().to_owned()),
-                _ => todo!(),
+        = "+" cmd:$(['A'..='Z']+) _? arg:arg()? {?
+            match (cmd, arg) {
+                ("PAUSE", _) => Ok(StoryEvent::Pause),
+                ("CLEAR", _) => Ok(StoryEvent::Clear),
+                ("INPUT", Some(arg)) => Ok(StoryEvent::Input(arg.to_owned())),
+                ("LABEL", Some(arg)) => Ok(StoryEvent::Label(arg.to_owned())),
+                ("GOTO", Some(arg)) => Ok(StoryEvent::Goto(arg.to_owned())),
+                ("SAVE", Some(arg)) => Ok(StoryEvent::Save(arg.to_owned())),
+                ("INCLUDE", Some(arg)) => Ok(StoryEvent::Include(arg.to_owned())),
+                ("INPUT" | "LABEL" | "GOTO" | "SAVE" | "INCLUDE", None) => Err("argument"),
+                _ => Err("known command"),
             }
         }
         / expected!("command")
 
+    // +IF:lhs op rhs GOTO target, e.g. +IF:health < 1 GOTO death
+    rule if_command() -> StoryEvent
+        = "+IF:" _? lhs:word() _ op:op() _ rhs:word() _ "GOTO" _ target:word() {
+            StoryEvent::Branch { lhs: lhs.to_owned(), op, rhs: rhs.to_owned(), target: target.to_owned() }
+        }
+
+    rule op() -> BranchOp
+        = "==" { BranchOp::Eq }
+        / "!=" { BranchOp::Ne }
+        / "<" { BranchOp::Lt }
+        / ">" { BranchOp::Gt }
+        / expected!("comparison operator")
+
+    // A single whitespace-delimited word, used by +IF's operands and target
+    rule word() -> &'input str
+        = $((!(['\n' | ' ' | '\t']) [_])+)
+
     // Arguments terminate at whitespace / newline
     rule arg() -> &'input str
         = ":" _? arg:$((!("\n"/_)  [_])+) { arg }
         / expected!("argument")
 
-    // rule args() -> Vec<String> 
+    // rule args() -> Vec<String>
     //     = args:( $([_]+) ++ (_* "," _*)) { args }
 
-    // Text must terminate before the next command (plus sign)
+    // Text must terminate before the next command (plus sign), and is tokenized
+    // into styled/interpolated fragments rather than kept as a flat string
     rule text() -> StoryEvent
-        = t:$((!"\n+" [_])+) { StoryEvent::Text(t.to_owned()) }
+        = f:fragment()+ { StoryEvent::Text(f.into_iter().flatten().collect()) }
         / expected!("text")
 
+    rule fragment() -> Vec<TextFragment>
+        = bold()
+        / italics()
+        / underline()
+        / interpolation()
+        / plain()
+        / marker_literal()
+
+    // *bold*
+    rule bold() -> Vec<TextFragment>
+        = "*" t:$((!("\n" / "*") [_])+) "*" {
+            vec![
+                TextFragment::BeginStyle(TextStyle::Bold),
+                TextFragment::Text(t.to_owned()),
+                TextFragment::EndStyle(TextStyle::Bold),
+            ]
+        }
+
+    // _italics_
+    rule italics() -> Vec<TextFragment>
+        = "_" t:$((!("\n" / "_") [_])+) "_" {
+            vec![
+                TextFragment::BeginStyle(TextStyle::Italics),
+                TextFragment::Text(t.to_owned()),
+                TextFragment::EndStyle(TextStyle::Italics),
+            ]
+        }
+
+    // ~underline~
+    rule underline() -> Vec<TextFragment>
+        = "~" t:$((!("\n" / "~") [_])+) "~" {
+            vec![
+                TextFragment::BeginStyle(TextStyle::Underline),
+                TextFragment::Text(t.to_owned()),
+                TextFragment::EndStyle(TextStyle::Underline),
+            ]
+        }
+
+    // A {label} placeholder, resolved against the game store at render time
+    rule interpolation() -> Vec<TextFragment>
+        = "{" label:$((!("}" / "\n") [_])+) "}" { vec![TextFragment::Interpolate(label.to_owned())] }
+
+    // A run of plain characters, up to the next marker or the end of the text event.
+    // A bare `+UPPERCASE` is always a command attempt (even with no preceding
+    // newline, e.g. at the very start of the story), never literal text - see
+    // `command()`'s note on malformed commands being a hard parse error.
+    rule plain() -> Vec<TextFragment>
+        = t:$((!("\n"* "+" ['A'..='Z'] / "*" / "_" / "~" / "{") [_])+) { vec![TextFragment::Text(t.to_owned())] }
+
+    // A lone `*`/`_`/`~`/`{` with no matching closing delimiter before the
+    // next newline. Falls back to literal text instead of failing the whole
+    // parse over stray punctuation (an apostrophe next to an `*`, a `{` that
+    // isn't an interpolation, ...)
+    rule marker_literal() -> Vec<TextFragment>
+        = t:$(['*' | '_' | '~' | '{']) { vec![TextFragment::Text(t.to_owned())] }
+
     pub rule story() -> Vec<StoryEvent>
         = l:(event() ** ("\n"+)) (_/"\n")* { l }
 }}
+
+#[cfg(test)]
+mod tests {
+    use super::story_parser::story;
+    use crate::text::TextFragment;
+    use crate::StoryEvent;
+
+    fn text_fragments(input: &str) -> Vec<TextFragment> {
+        match story(input).unwrap().into_iter().next().unwrap() {
+            StoryEvent::Text(f) => f,
+            event => panic!("expected a Text event, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn an_unmatched_style_marker_falls_back_to_literal_text() {
+        assert_eq!(
+            text_fragments("Hello * world"),
+            vec![
+                TextFragment::Text("Hello ".to_owned()),
+                TextFragment::Text("*".to_owned()),
+                TextFragment::Text(" world".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn an_unmatched_interpolation_brace_falls_back_to_literal_text() {
+        assert_eq!(
+            text_fragments("Hello {brace world"),
+            vec![
+                TextFragment::Text("Hello ".to_owned()),
+                TextFragment::Text("{".to_owned()),
+                TextFragment::Text("brace world".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn bold_italics_and_underline_each_wrap_their_text_in_a_begin_end_pair() {
+        use crate::text::TextStyle;
+
+        assert_eq!(
+            text_fragments("*bold*"),
+            vec![
+                TextFragment::BeginStyle(TextStyle::Bold),
+                TextFragment::Text("bold".to_owned()),
+                TextFragment::EndStyle(TextStyle::Bold),
+            ],
+        );
+        assert_eq!(
+            text_fragments("_italics_"),
+            vec![
+                TextFragment::BeginStyle(TextStyle::Italics),
+                TextFragment::Text("italics".to_owned()),
+                TextFragment::EndStyle(TextStyle::Italics),
+            ],
+        );
+        assert_eq!(
+            text_fragments("~underline~"),
+            vec![
+                TextFragment::BeginStyle(TextStyle::Underline),
+                TextFragment::Text("underline".to_owned()),
+                TextFragment::EndStyle(TextStyle::Underline),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpolation_produces_an_interpolate_fragment() {
+        assert_eq!(text_fragments("hp: {health}"), vec![
+            TextFragment::Text("hp: ".to_owned()),
+            TextFragment::Interpolate("health".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn a_command_missing_its_required_argument_is_a_parse_error_not_a_panic() {
+        assert!(story("+LABEL").is_err());
+        assert!(story("+GOTO").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::text::TextFragment;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The slot used by the quicksave/quickload key bindings
+pub const QUICKSAVE_SLOT: &str = "quick";
+
+/// Where a named save slot lives on disk, or `None` if `slot` isn't a bare
+/// file-name component. Save slots can come from `.story` files, which may
+/// be shared/untrusted, so this rejects path separators and `..` rather
+/// than letting a crafted slot name write outside the save directory.
+#[must_use]
+pub fn slot_path(slot: &str) -> Option<PathBuf> {
+    let is_bare_name = !slot.is_empty() && slot != "." && slot != ".." && !slot.contains(['/', '\\']);
+    is_bare_name.then(|| PathBuf::from(format!("save-{slot}.json")))
+}
+
+// A `+INPUT`/`+PAUSE` prompt the player was sitting at when the snapshot was
+// taken. `position` has already moved past the event that raised it (see
+// `run_app`'s `Update` arm), so on restore this must re-enter the wait
+// directly rather than resuming from `position`, or the prompt is silently
+// skipped and its `game_store` key never gets set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PendingWait {
+    Input(String),
+    Pause,
+}
+
+// The story program itself is regenerated from the `.story` file on load, so
+// only the mutable runtime state needs to be captured to resume a session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub position: usize,
+    pub game_store: HashMap<String, String>,
+    pub output: Vec<Vec<TextFragment>>,
+    // The line mid-typewriter (if any) when the snapshot was taken, and how
+    // far through it the animation had progressed. Without these, a save
+    // taken while a line is still animating would resume one beat past it,
+    // silently dropping that line from the transcript.
+    #[serde(default)]
+    pub current_response: Vec<TextFragment>,
+    #[serde(default)]
+    pub response_progress: usize,
+    // Set if the snapshot was taken while waiting on a `+INPUT`/`+PAUSE` prompt.
+    #[serde(default)]
+    pub pending_wait: Option<PendingWait>,
+}
+
+impl Snapshot {
+    /// # Errors
+    /// Returns an error if the snapshot cannot be written to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// # Errors
+    /// Returns an error if `path` cannot be read or does not contain a valid snapshot.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Snapshot> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_path_rejects_names_that_escape_the_save_directory() {
+        assert_eq!(slot_path(".."), None);
+        assert_eq!(slot_path("a/b"), None);
+        assert_eq!(slot_path("a\\b"), None);
+        assert_eq!(slot_path(""), None);
+    }
+
+    #[test]
+    fn slot_path_accepts_a_bare_file_name() {
+        assert_eq!(slot_path("quick"), Some(PathBuf::from("save-quick.json")));
+    }
+}
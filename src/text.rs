@@ -1,17 +1,16 @@
-pub struct TextEvent<'a> {
-    content: String,
-    fragments: Vec<TextFragment<'a>>,
-}
-
-pub enum TextFragment<'a> {
-    Text(&'a str),
-    Interpolate(String),
-    BeginStyle(TextStyle),
-    EndStyle(TextStyle),
-}
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TextStyle {
     Bold,
     Italics,
     Underline,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextFragment {
+    Text(String),
+    Interpolate(String),
+    BeginStyle(TextStyle),
+    EndStyle(TextStyle),
+}
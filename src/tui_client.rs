@@ -5,18 +5,29 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io, iter, mem, time::{Duration, Instant}};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    io, iter, mem, panic,
+    time::{Duration, Instant},
+};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, List, ListItem, Paragraph},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
 };
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-use text_adventure::{AppData, StoryEvent};
+use text_adventure::{
+    save::{slot_path, PendingWait, Snapshot, QUICKSAVE_SLOT},
+    text::{TextFragment, TextStyle},
+    AppData, StoryEvent,
+};
 
 enum InputMode {
     Disabled,
@@ -43,15 +54,57 @@ impl Default for UpdateState {
     }
 }
 
+// The matching strategy an incremental search cycles through with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMode {
+    Fuzzy,
+    Prefix,
+    Substring,
+}
+
+impl SearchMode {
+    fn cycle(self) -> Self {
+        match self {
+            SearchMode::Fuzzy => SearchMode::Prefix,
+            SearchMode::Prefix => SearchMode::Substring,
+            SearchMode::Substring => SearchMode::Fuzzy,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SearchMode::Fuzzy => "fuzzy",
+            SearchMode::Prefix => "prefix",
+            SearchMode::Substring => "substring",
+        }
+    }
+}
+
+struct SearchState {
+    query: String,
+    mode: SearchMode,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            mode: SearchMode::Fuzzy,
+        }
+    }
+}
+
 struct AppUi {
     input: String,
     input_mode: InputMode,
-    output: Vec<String>,
-    current_response: String,
+    output: Vec<Vec<TextFragment>>,
+    current_response: Vec<TextFragment>,
     response_time: Instant,
     response_progress: usize,
     update: UpdateState,
     label: String,
+    scroll: ListState,
+    search: Option<SearchState>,
 }
 
 impl Default for AppUi {
@@ -60,37 +113,84 @@ impl Default for AppUi {
             input: String::default(),
             input_mode: InputMode::default(),
             output: Vec::default(),
-            current_response: String::default(),
+            current_response: Vec::default(),
             // HACK: Unnecessary system call
             response_time: Instant::now(),
             response_progress: usize::default(),
             update: UpdateState::default(),
             label: String::default(),
+            scroll: ListState::default(),
+            search: None,
         }
     }
 }
 
+// Restores the terminal to its normal state; used by both the panic hook and
+// `TerminalGuard` so a crash never leaves the shell in raw/alternate-screen mode
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+}
+
+// Wraps `std::panic::set_hook` so a panic restores the terminal before the
+// default hook prints its backtrace, instead of leaving a garbled shell
+fn install_panic_hook() {
+    let original = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        original(info);
+    }));
+}
+
+// RAII guard that restores the terminal on drop, covering early returns as
+// well as the normal end of `main`
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    // create app and run it
-    let app_data = AppData::default();
-    let app_ui = AppUi::default();
-    let res = run_app(&mut terminal, app_ui, app_data);
-
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    install_panic_hook();
+
+    let mut args = env::args().skip(1);
+    let story_path = args.next().unwrap_or_else(|| "entry.story".to_owned());
+    let snapshot_path = args.next();
+
+    // Load (and report any error for) the story before touching the
+    // terminal, so a bad story file prints a plain, readable message
+    let mut app_data = match AppData::load(&story_path) {
+        Ok(app_data) => app_data,
+        Err(err) => {
+            eprintln!("failed to load {story_path}: {err}");
+            return Ok(());
+        }
+    };
+    let mut app_ui = AppUi::default();
+    if let Some(path) = snapshot_path {
+        if let Ok(snapshot) = Snapshot::load(path) {
+            restore_snapshot(&mut app_ui, &mut app_data, snapshot);
+        }
+    }
+
+    // Scoped so `_terminal_guard` restores the terminal before `res` is
+    // inspected below — otherwise an `Err` from `run_app` gets `eprintln!`'d
+    // while still inside the alternate screen, where the user never sees it
+    let res = {
+        // setup terminal
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        let _terminal_guard = TerminalGuard;
+
+        let res = run_app(&mut terminal, app_ui, app_data);
+        terminal.show_cursor()?;
+        res
+    };
 
     if let Err(err) = res {
         eprintln!("{:?}", err);
@@ -99,6 +199,97 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Number of entries a PageUp/PageDown jumps over
+const SCROLL_PAGE: isize = 10;
+
+// Moves a list selection by `delta`, clamping to the valid range for `total`
+// entries; defaults to the last (most recent) entry when nothing is selected
+fn move_selection(state: &mut ListState, delta: isize, total: usize) {
+    if total == 0 {
+        state.select(None);
+        return;
+    }
+    let current = state.selected().unwrap_or(total - 1);
+    let next = if delta.is_negative() {
+        current.saturating_sub(delta.unsigned_abs())
+    } else {
+        (current + delta.unsigned_abs()).min(total - 1)
+    };
+    state.select(Some(next));
+}
+
+fn matching_count(output: &[Vec<TextFragment>], search: &SearchState, game_store: &HashMap<String, String>) -> usize {
+    output
+        .iter()
+        .filter(|f| {
+            let text = line_text(f, game_store);
+            search_spans(&text, &search.query, search.mode).is_some()
+        })
+        .count()
+}
+
+fn take_snapshot(app_ui: &AppUi, app_data: &AppData) -> Snapshot {
+    let pending_wait = match &app_ui.input_mode {
+        InputMode::Input => Some(PendingWait::Input(app_ui.label.clone())),
+        InputMode::Pause => Some(PendingWait::Pause),
+        InputMode::Disabled => None,
+    };
+
+    Snapshot {
+        position: app_data.position,
+        game_store: app_data.game_store.clone(),
+        output: app_ui.output.clone(),
+        current_response: app_ui.current_response.clone(),
+        response_progress: app_ui.response_progress,
+        pending_wait,
+    }
+}
+
+fn restore_snapshot(app_ui: &mut AppUi, app_data: &mut AppData, snapshot: Snapshot) {
+    app_data.position = snapshot.position;
+    app_data.game_store = snapshot.game_store;
+    app_ui.output = snapshot.output;
+    app_ui.current_response = snapshot.current_response;
+    app_ui.response_progress = snapshot.response_progress;
+
+    // A snapshot taken mid-`+INPUT`/`+PAUSE` must re-enter that wait rather
+    // than falling through to `Update`, which already sits one instruction
+    // past the prompt that raised it (see the `Update` arm below) and would
+    // silently skip asking again.
+    match snapshot.pending_wait {
+        Some(PendingWait::Input(label)) => {
+            app_ui.label = label;
+            app_ui.input_mode = InputMode::Input;
+            app_ui.update = UpdateState::Wait;
+        }
+        Some(PendingWait::Pause) => {
+            app_ui.input_mode = InputMode::Pause;
+            app_ui.update = UpdateState::Wait;
+        }
+        // Resume the typewriter where it left off rather than restarting it,
+        // so a save taken mid-response doesn't lose or replay that line
+        None if app_ui.current_response.is_empty() => {
+            app_ui.input_mode = InputMode::Disabled;
+            app_ui.update = UpdateState::Update;
+        }
+        None => {
+            app_ui.input_mode = InputMode::Disabled;
+            // `response_progress` comes straight from a (possibly hand-edited)
+            // save file, so it can't be trusted to fall within the response
+            // it's meant to index into; clamp it rather than risk an
+            // overflowing multiplication below.
+            let length = fragments_char_count(&app_ui.current_response, &app_data.game_store);
+            app_ui.response_progress = app_ui.response_progress.min(length);
+            let elapsed = Duration::from_millis(app_ui.response_progress as u64 * 10);
+            app_ui.response_time = Instant::now().checked_sub(elapsed).unwrap_or_else(Instant::now);
+            app_ui.update = UpdateState::Responding;
+        }
+    }
+
+    app_ui.scroll.select(None);
+    app_ui.search = None;
+}
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app_ui: AppUi,
@@ -106,30 +297,11 @@ fn run_app<B: Backend>(
 ) -> io::Result<()> {
     loop {
         match app_ui.update {
-            UpdateState::Update => match app_data.story.pop_front() {
-                Some(event) => match event {
-                    StoryEvent::Text(t) => {
-                        app_ui.current_response = t;
-                        app_ui.response_time = Instant::now();
-                        app_ui.input_mode = InputMode::Disabled;
-                        app_ui.update = UpdateState::Responding;
-                    }
-                    StoryEvent::Input(_) => {
-                        app_ui.input_mode = InputMode::Input;
-                        app_ui.update = UpdateState::Wait;
-                    }
-                    StoryEvent::Pause => {
-                        app_ui.input_mode = InputMode::Pause;
-                        app_ui.update = UpdateState::Wait;
-                    }
-                    StoryEvent::Clear => {
-                        app_ui.output.clear();
-                    }
-                },
-                None => {
+            UpdateState::Update => {
+                if !advance_story(&mut app_ui, &mut app_data) {
                     return Ok(());
                 }
-            },
+            }
 
             UpdateState::HandleInput => {
                 app_data
@@ -141,94 +313,599 @@ fn run_app<B: Backend>(
                 app_ui.update = UpdateState::Update;
             }
 
-            UpdateState::Responding => {
-                let progress = app_ui.response_time.elapsed().as_millis() as usize / 10;
-                if progress >= app_ui.current_response.len() {
-                    app_ui.output.push(mem::take(&mut app_ui.current_response));
-                    app_ui.update = UpdateState::Update;
-                    app_ui.response_progress = 0;
-                } else {
-                    app_ui.response_progress = progress;
-                }
-            }
+            UpdateState::Responding => advance_typewriter(&mut app_ui, &app_data),
 
             UpdateState::Wait => {}
         }
 
-        terminal.draw(|f| ui(f, &app_ui))?;
+        terminal.draw(|f| ui(f, &mut app_ui, &app_data.game_store))?;
 
         if event::poll(Duration::ZERO)? {
             if let Event::Key(key) = event::read()? {
-                if let KeyCode::Home = key.code {
+                if handle_key(key.code, &mut app_ui, &mut app_data) {
                     return Ok(());
                 }
+            }
+        }
+    }
+}
+
+// Runs the event at `app_data.position` and advances it; returns `false` once
+// the story has run out of events (the caller should then quit)
+fn advance_story(app_ui: &mut AppUi, app_data: &mut AppData) -> bool {
+    let Some(event) = app_data.story.get(app_data.position) else {
+        return false;
+    };
 
-                match app_ui.input_mode {
-                    InputMode::Input => match key.code {
-                        KeyCode::Enter => {
-                            app_ui.input_mode = InputMode::Disabled;
-                            app_ui.update = UpdateState::HandleInput;
-                        }
-                        KeyCode::Char(c) => {
-                            app_ui.input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            app_ui.input.pop();
-                        }
-                        _ => {}
-                    },
-                    InputMode::Disabled => match key.code {
-                        _ => {}
-                    },
-                    InputMode::Pause => {
-                        app_ui.update = UpdateState::Update;
-                    }
+    let mut next = app_data.position + 1;
+
+    match event {
+        StoryEvent::Text(t) => {
+            app_ui.current_response.clone_from(t);
+            app_ui.response_time = Instant::now();
+            app_ui.input_mode = InputMode::Disabled;
+            app_ui.update = UpdateState::Responding;
+        }
+        StoryEvent::Input(label) => {
+            app_ui.label.clone_from(label);
+            app_ui.input_mode = InputMode::Input;
+            app_ui.update = UpdateState::Wait;
+        }
+        StoryEvent::Pause => {
+            app_ui.input_mode = InputMode::Pause;
+            app_ui.update = UpdateState::Wait;
+        }
+        StoryEvent::Clear => {
+            app_ui.output.clear();
+        }
+        // `Include` is spliced away by the loader before the story ever reaches here
+        StoryEvent::Label(_) | StoryEvent::Include(_) => {}
+        StoryEvent::Save(slot) => {
+            if let Some(path) = slot_path(slot) {
+                let snapshot = take_snapshot(app_ui, app_data);
+                let _ = snapshot.save(path);
+            }
+        }
+        StoryEvent::Goto(target) => {
+            if let Some(&label) = app_data.labels.get(target) {
+                next = label;
+            }
+        }
+        StoryEvent::Branch { lhs, op, rhs, target } => {
+            let lhs = app_data.resolve(lhs);
+            let rhs = app_data.resolve(rhs);
+            if op.evaluate(&lhs, &rhs) {
+                if let Some(&label) = app_data.labels.get(target) {
+                    next = label;
                 }
             }
         }
     }
+
+    app_data.position = next;
+    true
+}
+
+// Advances the typewriter effect by however much time has elapsed, landing
+// the response in `output` once it's fully revealed
+fn advance_typewriter(app_ui: &mut AppUi, app_data: &AppData) {
+    let progress = app_ui.response_time.elapsed().as_millis() as usize / 10;
+    let length = fragments_char_count(&app_ui.current_response, &app_data.game_store);
+    if progress >= length {
+        app_ui.output.push(mem::take(&mut app_ui.current_response));
+        app_ui.update = UpdateState::Update;
+        app_ui.response_progress = 0;
+    } else {
+        app_ui.response_progress = progress;
+    }
+}
+
+// Handles a single key press; returns `true` if the user asked to quit
+fn handle_key(code: KeyCode, app_ui: &mut AppUi, app_data: &mut AppData) -> bool {
+    if let KeyCode::Home = code {
+        return true;
+    }
+
+    if let KeyCode::F(5) = code {
+        if let Some(path) = slot_path(QUICKSAVE_SLOT) {
+            let snapshot = take_snapshot(app_ui, app_data);
+            let _ = snapshot.save(path);
+        }
+        return false;
+    }
+
+    if let KeyCode::F(9) = code {
+        if let Some(path) = slot_path(QUICKSAVE_SLOT) {
+            if let Ok(snapshot) = Snapshot::load(path) {
+                restore_snapshot(app_ui, app_data, snapshot);
+            }
+        }
+        // Already fully handled - falling through to the mode dispatch below
+        // would treat this same keystroke as "any key" input to whatever mode
+        // `restore_snapshot` just set (e.g. immediately un-pausing a snapshot
+        // that was taken mid-`+PAUSE`).
+        return false;
+    }
+
+    match app_ui.input_mode {
+        InputMode::Input => handle_input_mode_key(code, app_ui),
+        InputMode::Disabled => handle_disabled_mode_key(code, app_ui, app_data),
+        InputMode::Pause => {
+            app_ui.update = UpdateState::Update;
+        }
+    }
+
+    false
+}
+
+fn handle_input_mode_key(code: KeyCode, app_ui: &mut AppUi) {
+    match code {
+        KeyCode::Enter => {
+            app_ui.input_mode = InputMode::Disabled;
+            app_ui.update = UpdateState::HandleInput;
+        }
+        KeyCode::Char(c) => {
+            app_ui.input.push(c);
+        }
+        KeyCode::Backspace => {
+            app_ui.input.pop();
+        }
+        _ => {}
+    }
+}
+
+fn handle_disabled_mode_key(code: KeyCode, app_ui: &mut AppUi, app_data: &AppData) {
+    if let Some(search) = &mut app_ui.search {
+        match code {
+            KeyCode::Esc => {
+                app_ui.search = None;
+                app_ui.scroll.select(None);
+            }
+            KeyCode::Tab => {
+                search.mode = search.mode.cycle();
+                app_ui.scroll.select(None);
+            }
+            KeyCode::Char(c) => {
+                search.query.push(c);
+                app_ui.scroll.select(None);
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                app_ui.scroll.select(None);
+            }
+            KeyCode::Up => move_selection(
+                &mut app_ui.scroll,
+                -1,
+                matching_count(&app_ui.output, search, &app_data.game_store),
+            ),
+            KeyCode::Down => move_selection(
+                &mut app_ui.scroll,
+                1,
+                matching_count(&app_ui.output, search, &app_data.game_store),
+            ),
+            KeyCode::PageUp => move_selection(
+                &mut app_ui.scroll,
+                -SCROLL_PAGE,
+                matching_count(&app_ui.output, search, &app_data.game_store),
+            ),
+            KeyCode::PageDown => move_selection(
+                &mut app_ui.scroll,
+                SCROLL_PAGE,
+                matching_count(&app_ui.output, search, &app_data.game_store),
+            ),
+            _ => {}
+        }
+    } else {
+        match code {
+            KeyCode::Char('/') => {
+                app_ui.search = Some(SearchState::default());
+                app_ui.scroll.select(None);
+            }
+            KeyCode::Up => {
+                move_selection(&mut app_ui.scroll, -1, app_ui.output.len() + 1);
+            }
+            KeyCode::Down => {
+                move_selection(&mut app_ui.scroll, 1, app_ui.output.len() + 1);
+            }
+            KeyCode::PageUp => {
+                move_selection(&mut app_ui.scroll, -SCROLL_PAGE, app_ui.output.len() + 1);
+            }
+            KeyCode::PageDown => {
+                move_selection(&mut app_ui.scroll, SCROLL_PAGE, app_ui.output.len() + 1);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Maps a story text style to the tui modifier that renders it
+fn style_modifier(style: TextStyle) -> Modifier {
+    match style {
+        TextStyle::Bold => Modifier::BOLD,
+        TextStyle::Italics => Modifier::ITALIC,
+        TextStyle::Underline => Modifier::UNDERLINED,
+    }
+}
+
+// The text a fragment contributes to the rendered response, resolving
+// interpolations against the game store (missing keys render as empty)
+fn fragment_text<'a>(fragment: &'a TextFragment, game_store: &'a HashMap<String, String>) -> &'a str {
+    match fragment {
+        TextFragment::Text(t) => t,
+        TextFragment::Interpolate(key) => game_store.get(key).map_or("", String::as_str),
+        TextFragment::BeginStyle(_) | TextFragment::EndStyle(_) => "",
+    }
+}
+
+// Number of renderable characters in a fragment list, counted as chars
+// (not bytes) so the typewriter progress stays Unicode-safe
+fn fragments_char_count(fragments: &[TextFragment], game_store: &HashMap<String, String>) -> usize {
+    fragments
+        .iter()
+        .map(|f| fragment_text(f, game_store).chars().count())
+        .sum()
+}
+
+// Renders up to `max_chars` characters of a fragment list as styled spans,
+// tracking the active style stack across Begin/EndStyle markers
+fn fragments_to_spans<'a>(
+    fragments: &'a [TextFragment],
+    game_store: &'a HashMap<String, String>,
+    max_chars: usize,
+) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut modifier = Modifier::empty();
+    let mut remaining = max_chars;
+
+    for fragment in fragments {
+        match fragment {
+            TextFragment::BeginStyle(style) => modifier.insert(style_modifier(*style)),
+            TextFragment::EndStyle(style) => modifier.remove(style_modifier(*style)),
+            TextFragment::Text(_) | TextFragment::Interpolate(_) => {
+                if remaining == 0 {
+                    break;
+                }
+                let text = fragment_text(fragment, game_store);
+                let taken: String = text.chars().take(remaining).collect();
+                remaining -= taken.chars().count();
+                if !taken.is_empty() {
+                    spans.push(Span::styled(taken, Style::default().add_modifier(modifier)));
+                }
+            }
+        }
+    }
+
+    spans
+}
+
+// The plain text a line of fragments renders as, ignoring styling
+fn line_text(fragments: &[TextFragment], game_store: &HashMap<String, String>) -> String {
+    fragments
+        .iter()
+        .map(|f| fragment_text(f, game_store))
+        .collect()
+}
+
+// Which graphemes of `line` the query matches, grapheme-indexed so
+// highlighting stays correct for multi-byte characters. `None` means no match.
+fn grapheme_match_flags(line: &[&str], query: &[&str], mode: SearchMode) -> Option<Vec<bool>> {
+    if query.is_empty() {
+        return Some(vec![false; line.len()]);
+    }
+
+    match mode {
+        SearchMode::Prefix => {
+            if query.len() > line.len() || line[..query.len()] != *query {
+                return None;
+            }
+            let mut flags = vec![false; line.len()];
+            flags[..query.len()].fill(true);
+            Some(flags)
+        }
+        SearchMode::Substring => {
+            if query.len() > line.len() {
+                return None;
+            }
+            let start = line.windows(query.len()).position(|w| w == query)?;
+            let mut flags = vec![false; line.len()];
+            flags[start..start + query.len()].fill(true);
+            Some(flags)
+        }
+        SearchMode::Fuzzy => {
+            let mut flags = vec![false; line.len()];
+            let mut next = 0;
+            for (i, g) in line.iter().enumerate() {
+                if next < query.len() && *g == query[next] {
+                    flags[i] = true;
+                    next += 1;
+                }
+            }
+            (next == query.len()).then_some(flags)
+        }
+    }
+}
+
+// Renders `text` as spans with the matched portion highlighted, or `None`
+// when `text` doesn't match `query` under `mode` (the line should be hidden)
+fn search_spans(text: &str, query: &str, mode: SearchMode) -> Option<Vec<Span<'static>>> {
+    let line: Vec<&str> = text.graphemes(true).collect();
+    let query: Vec<&str> = query.graphemes(true).collect();
+    let flags = grapheme_match_flags(&line, &query, mode)?;
+
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_flag = false;
+    for (grapheme, &flag) in line.iter().zip(flags.iter()) {
+        if current.is_empty() {
+            current_flag = flag;
+        } else if flag != current_flag {
+            spans.push(highlighted_span(mem::take(&mut current), current_flag));
+            current_flag = flag;
+        }
+        current.push_str(grapheme);
+    }
+    if !current.is_empty() {
+        spans.push(highlighted_span(current, current_flag));
+    }
+    Some(spans)
+}
+
+fn highlighted_span(text: String, highlighted: bool) -> Span<'static> {
+    let style = if highlighted {
+        Style::default().bg(Color::Yellow).fg(Color::Black)
+    } else {
+        Style::default()
+    };
+    Span::styled(text, style)
 }
 
 #[allow(clippy::cast_possible_truncation)]
-fn ui<B: Backend>(f: &mut Frame<B>, app: &AppUi) {
+fn ui<B: Backend>(f: &mut Frame<B>, app: &mut AppUi, game_store: &HashMap<String, String>) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
         .split(f.size());
 
-    let (content, style) = match app.input_mode {
-        InputMode::Disabled => (
-            Span::raw("..."),
-            Style::default(),
-        ),
-        InputMode::Input => (
-            Span::raw(&app.input),
-            Style::default().fg(Color::Yellow),
-        ),
-        InputMode::Pause => (
-            Span::raw("Press any key to continue."),
-            Style::default().fg(Color::Green),
-        ),
-    };
-    let text = Spans::from(vec![Span::raw("> "), content]);
-    let paragraph = Paragraph::new(text)
-        .style(style)
-        .block(Block::default());
-    f.render_widget(paragraph, chunks[1]);
-
-    match app.input_mode {
-        InputMode::Input => f.set_cursor(chunks[1].x + 2 + app.input.width() as u16, chunks[1].y),
-        _ => {}
+    if let Some(search) = &app.search {
+        let prefix = format!("/{} ", search.mode.label());
+        let text = Spans::from(vec![
+            Span::styled(prefix, Style::default().fg(Color::Cyan)),
+            Span::raw(&search.query),
+        ]);
+        let paragraph = Paragraph::new(text).block(Block::default());
+        f.render_widget(paragraph, chunks[1]);
+    } else {
+        let (content, style) = match app.input_mode {
+            InputMode::Disabled => (Span::raw("..."), Style::default()),
+            InputMode::Input => (Span::raw(&app.input), Style::default().fg(Color::Yellow)),
+            InputMode::Pause => (
+                Span::raw("Press any key to continue."),
+                Style::default().fg(Color::Green),
+            ),
+        };
+        let text = Spans::from(vec![Span::raw("> "), content]);
+        let paragraph = Paragraph::new(text)
+            .style(style)
+            .block(Block::default());
+        f.render_widget(paragraph, chunks[1]);
+
+        if let InputMode::Input = app.input_mode {
+            f.set_cursor(chunks[1].x + 2 + app.input.width() as u16, chunks[1].y);
+        }
     }
 
-    let current = &app.current_response[0..app.response_progress];
-    let output = List::new(
+    let items: Vec<ListItem> = if let Some(search) = &app.search {
+        app.output
+            .iter()
+            .filter_map(|f| {
+                let text = line_text(f, game_store);
+                search_spans(&text, &search.query, search.mode).map(|spans| ListItem::new(Spans::from(spans)))
+            })
+            .collect()
+    } else {
+        let current = fragments_to_spans(&app.current_response, game_store, app.response_progress);
         app.output
             .iter()
-            .map(|t| ListItem::new(t.as_str()))
-            .chain(iter::once(ListItem::new(current)))
-            .collect::<Vec<_>>(),
-    );
+            .map(|f| ListItem::new(Spans::from(fragments_to_spans(f, game_store, usize::MAX))))
+            .chain(iter::once(ListItem::new(Spans::from(current))))
+            .collect()
+    };
+
+    let output = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(output, chunks[0], &mut app.scroll);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn graphemes(s: &str) -> Vec<&str> {
+        s.graphemes(true).collect()
+    }
+
+    #[test]
+    fn prefix_mode_matches_only_at_the_start() {
+        let line = graphemes("hello world");
+        assert_eq!(
+            grapheme_match_flags(&line, &graphemes("hello"), SearchMode::Prefix),
+            Some(vec![true, true, true, true, true, false, false, false, false, false, false]),
+        );
+        assert_eq!(grapheme_match_flags(&line, &graphemes("world"), SearchMode::Prefix), None);
+    }
+
+    #[test]
+    fn substring_mode_matches_anywhere_contiguously() {
+        let line = graphemes("hello world");
+        let flags = grapheme_match_flags(&line, &graphemes("world"), SearchMode::Substring).unwrap();
+        assert_eq!(flags, vec![false, false, false, false, false, false, true, true, true, true, true]);
+        assert_eq!(grapheme_match_flags(&line, &graphemes("wrd"), SearchMode::Substring), None);
+    }
+
+    #[test]
+    fn fuzzy_mode_matches_an_in_order_subsequence() {
+        let line = graphemes("hello world");
+        assert!(grapheme_match_flags(&line, &graphemes("hlowrd"), SearchMode::Fuzzy).is_some());
+        assert_eq!(grapheme_match_flags(&line, &graphemes("dlrow"), SearchMode::Fuzzy), None);
+    }
+
+    #[test]
+    fn matching_is_grapheme_aware_not_byte_aware() {
+        // "é" here is a combining sequence (e + U+0301), two chars / one grapheme
+        let line = graphemes("caf\u{0065}\u{0301}");
+        let query = graphemes("\u{0065}\u{0301}");
+        let flags = grapheme_match_flags(&line, &query, SearchMode::Substring).unwrap();
+        assert_eq!(flags, vec![false, false, false, true]);
+    }
+
+    fn new_app_data() -> AppData {
+        AppData {
+            story: Vec::new(),
+            labels: HashMap::new(),
+            position: 0,
+            game_store: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn saving_mid_response_does_not_lose_the_animating_line() {
+        let mut app_ui = AppUi::default();
+        let app_data = new_app_data();
+
+        let line = vec![TextFragment::Text("hello world".to_owned())];
+        app_ui.current_response = line.clone();
+        app_ui.response_progress = 5; // stopped partway through the typewriter
+
+        let snapshot = take_snapshot(&app_ui, &app_data);
+
+        let mut restored_ui = AppUi::default();
+        let mut restored_data = new_app_data();
+        restore_snapshot(&mut restored_ui, &mut restored_data, snapshot);
+
+        // The line is still being typed, so it shouldn't be dropped: it's
+        // either still animating or already landed in `output`
+        assert!(matches!(restored_ui.update, UpdateState::Responding));
+        assert_eq!(restored_ui.current_response, line);
+        assert_eq!(restored_ui.response_progress, 5);
+
+        // Letting the typewriter run to completion lands the line in output,
+        // exactly as if the save/restore never happened
+        let length = fragments_char_count(&restored_ui.current_response, &restored_data.game_store);
+        restored_ui.response_progress = length;
+        restored_ui.output.push(mem::take(&mut restored_ui.current_response));
+        assert_eq!(restored_ui.output, vec![line]);
+    }
+
+    #[test]
+    fn saving_during_an_input_prompt_re_asks_on_restore() {
+        let mut app_ui = AppUi::default();
+        let mut app_data = new_app_data();
+        // Mirrors what the `Update` arm does for `StoryEvent::Input`: position
+        // is already past the prompt by the time we're sitting in `Wait`
+        app_data.position = 3;
+        app_ui.input_mode = InputMode::Input;
+        app_ui.label = "player_name".to_owned();
+        app_ui.update = UpdateState::Wait;
+
+        let snapshot = take_snapshot(&app_ui, &app_data);
+
+        let mut restored_ui = AppUi::default();
+        let mut restored_data = new_app_data();
+        restore_snapshot(&mut restored_ui, &mut restored_data, snapshot);
+
+        assert!(matches!(restored_ui.update, UpdateState::Wait));
+        assert!(matches!(restored_ui.input_mode, InputMode::Input));
+        assert_eq!(restored_ui.label, "player_name");
+        assert_eq!(restored_data.position, 3);
+
+        // Answering the prompt now stores it under the label that was
+        // pending when the snapshot was taken, exactly as if the prompt had
+        // never been interrupted
+        restored_ui.input = "Ada".to_owned();
+        restored_ui.input_mode = InputMode::Disabled;
+        restored_ui.update = UpdateState::HandleInput;
+        restored_data
+            .game_store
+            .insert(mem::take(&mut restored_ui.label), mem::take(&mut restored_ui.input));
+        assert_eq!(restored_data.game_store.get("player_name"), Some(&"Ada".to_owned()));
+    }
+
+    #[test]
+    fn f9_restoring_a_paused_snapshot_does_not_immediately_unpause() {
+        let mut app_ui = AppUi::default();
+        let app_data = new_app_data();
+        app_ui.input_mode = InputMode::Pause;
+        app_ui.update = UpdateState::Wait;
+
+        let snapshot = take_snapshot(&app_ui, &app_data);
+        let path = slot_path(QUICKSAVE_SLOT).unwrap();
+        snapshot.save(&path).unwrap();
+
+        // The player had moved on to something else before pressing F9
+        let mut app_ui = AppUi::default();
+        let mut app_data = new_app_data();
+
+        // F9 both restores the snapshot and is itself a keystroke - it must
+        // not be treated as "any key" input to the mode `restore_snapshot`
+        // just set, or the just-loaded pause prompt is skipped on the spot
+        let quit = handle_key(KeyCode::F(9), &mut app_ui, &mut app_data);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(!quit);
+        assert!(matches!(app_ui.input_mode, InputMode::Pause));
+        assert!(matches!(app_ui.update, UpdateState::Wait));
+    }
+
+    #[test]
+    fn fragment_text_resolves_interpolation_and_falls_back_to_empty_for_a_missing_key() {
+        let mut game_store = HashMap::new();
+        game_store.insert("name".to_owned(), "Alex".to_owned());
+
+        assert_eq!(fragment_text(&TextFragment::Interpolate("name".to_owned()), &game_store), "Alex");
+        assert_eq!(fragment_text(&TextFragment::Interpolate("missing".to_owned()), &game_store), "");
+        assert_eq!(fragment_text(&TextFragment::Text("plain".to_owned()), &game_store), "plain");
+    }
+
+    #[test]
+    fn fragments_char_count_counts_chars_not_bytes() {
+        let game_store = HashMap::new();
+        // Each "é" here is 2 bytes but 1 char
+        let fragments = vec![TextFragment::Text("café".to_owned())];
+        assert_eq!(fragments_char_count(&fragments, &game_store), 4);
+    }
+
+    #[test]
+    fn fragments_to_spans_stacks_and_unwinds_overlapping_styles() {
+        let game_store = HashMap::new();
+        let fragments = vec![
+            TextFragment::BeginStyle(TextStyle::Bold),
+            TextFragment::Text("bo".to_owned()),
+            TextFragment::BeginStyle(TextStyle::Italics),
+            TextFragment::Text("th".to_owned()),
+            TextFragment::EndStyle(TextStyle::Bold),
+            TextFragment::Text("it".to_owned()),
+            TextFragment::EndStyle(TextStyle::Italics),
+        ];
 
-    f.render_widget(output, chunks[0]);
+        let spans = fragments_to_spans(&fragments, &game_store, usize::MAX);
+
+        assert_eq!(spans[0].content, "bo");
+        assert_eq!(spans[0].style.add_modifier, Modifier::BOLD);
+
+        assert_eq!(spans[1].content, "th");
+        assert_eq!(spans[1].style.add_modifier, Modifier::BOLD | Modifier::ITALIC);
+
+        // Bold ended but italics is still active, so it must not be dropped too
+        assert_eq!(spans[2].content, "it");
+        assert_eq!(spans[2].style.add_modifier, Modifier::ITALIC);
+    }
+
+    #[test]
+    fn fragments_to_spans_truncates_at_max_chars() {
+        let game_store = HashMap::new();
+        let fragments = vec![TextFragment::Text("hello world".to_owned())];
+        let spans = fragments_to_spans(&fragments, &game_store, 5);
+        assert_eq!(spans[0].content, "hello");
+    }
 }